@@ -0,0 +1,389 @@
+use anyhow::{Context, Result};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{body::Body, extract::State, http::Request, middleware::Next, response::Response};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{extract_token, AppState, AuthConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Viewer,
+    ReadOnly,
+}
+
+impl Role {
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Viewer => "viewer",
+            Role::ReadOnly => "read_only",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "admin" => Role::Admin,
+            "viewer" => Role::Viewer,
+            _ => Role::ReadOnly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TokenKind {
+    Access,
+    Refresh,
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct UserRow {
+    id: Uuid,
+    username: String,
+    password_hash: String,
+    role: String,
+    group_tag: Option<String>,
+}
+
+/// 已认证用户，由 `require_auth` 中间件附加到请求扩展上。
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: Uuid,
+    pub username: String,
+    pub role: Role,
+    pub group_tag: Option<String>,
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Claims {
+    sub: Uuid,
+    sid: Uuid,
+    role: String,
+    kind: TokenKind,
+    exp: usize,
+    iat: usize,
+}
+
+#[derive(Clone)]
+pub struct AuthSettings {
+    session_minutes: i64,
+    refresh_minutes: i64,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+pub fn build_auth_settings(config: Option<&AuthConfig>) -> AuthSettings {
+    let secret = config
+        .and_then(|a| a.secret.clone())
+        .unwrap_or_else(|| "change-me-please".to_string());
+
+    let session_minutes = config.and_then(|a| a.session_minutes).unwrap_or(5).max(1);
+    let refresh_minutes = config
+        .and_then(|a| a.refresh_days)
+        .unwrap_or(14)
+        .max(1)
+        * 24
+        * 60;
+
+    AuthSettings {
+        session_minutes,
+        refresh_minutes,
+        encoding: EncodingKey::from_secret(secret.as_bytes()),
+        decoding: DecodingKey::from_secret(secret.as_bytes()),
+    }
+}
+
+/// 一次登录签发的令牌对：短期访问令牌 + 长期刷新令牌，二者绑定同一个会话。
+pub struct TokenPair {
+    pub access_token: String,
+    pub access_expires_at: i64,
+    pub refresh_token: String,
+}
+
+/// 登录成功后返回给调用方的结果。
+pub struct LoginResult {
+    pub tokens: TokenPair,
+    pub username: String,
+}
+
+/// 校验用户名/密码并在成功时创建一个新会话，返回绑定该会话的令牌对。
+pub async fn login(
+    pool: &Pool<Postgres>,
+    username: &str,
+    password: &str,
+    settings: &AuthSettings,
+) -> Result<Option<LoginResult>> {
+    let Some(user) = verify_credentials(pool, username, password).await? else {
+        return Ok(None);
+    };
+    let tokens = issue_session(pool, &user, settings).await?;
+    Ok(Some(LoginResult {
+        username: user.username.clone(),
+        tokens,
+    }))
+}
+
+/// 若 `users` 表为空，按配置种入一个初始管理员账号，保证既有部署升级后仍能登录。
+pub async fn bootstrap_admin(pool: &Pool<Postgres>, config: Option<&AuthConfig>) -> Result<()> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+        .context("无法统计 users 表")?;
+
+    if count > 0 {
+        return Ok(());
+    }
+
+    let username = config
+        .and_then(|a| a.username.clone())
+        .unwrap_or_else(|| "admin".to_string());
+    let password = config
+        .and_then(|a| a.password.clone())
+        .unwrap_or_else(|| "studyguardian".to_string());
+
+    let password_hash = hash_password(&password)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO users (id, username, password_hash, role, group_tag, created_at)
+        VALUES ($1, $2, $3, 'admin', NULL, now())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(&username)
+    .bind(&password_hash)
+    .execute(pool)
+    .await
+    .context("写入初始管理员账号失败")?;
+
+    tracing::info!("已为空的 users 表种入初始管理员账号: {}", username);
+    Ok(())
+}
+
+fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| anyhow::anyhow!("密码哈希失败: {}", err))
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+}
+
+/// 校验用户名/密码是否匹配数据库中的记录，成功时返回对应的用户（此时尚未绑定会话）。
+async fn verify_credentials(
+    pool: &Pool<Postgres>,
+    username: &str,
+    password: &str,
+) -> Result<Option<UserRow>> {
+    let row = sqlx::query_as::<_, UserRow>(
+        r#"SELECT id, username, password_hash, role, group_tag FROM users WHERE username = $1"#,
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await
+    .context("查询用户失败")?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    if !verify_password(password, &row.password_hash) {
+        return Ok(None);
+    }
+
+    Ok(Some(row))
+}
+
+async fn load_user(pool: &Pool<Postgres>, id: Uuid) -> Result<Option<UserRow>> {
+    sqlx::query_as::<_, UserRow>(
+        r#"SELECT id, username, password_hash, role, group_tag FROM users WHERE id = $1"#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .context("查询用户失败")
+}
+
+fn sign(claims: &Claims, settings: &AuthSettings) -> Result<String> {
+    encode(&Header::default(), claims, &settings.encoding).context("签发令牌失败")
+}
+
+/// 创建一条新会话记录，并签发绑定该会话的访问令牌与刷新令牌。
+async fn issue_session(
+    pool: &Pool<Postgres>,
+    user: &UserRow,
+    settings: &AuthSettings,
+) -> Result<TokenPair> {
+    let session_id = Uuid::new_v4();
+    let now = Utc::now();
+    let refresh_exp = now + chrono::Duration::minutes(settings.refresh_minutes);
+
+    sqlx::query(
+        r#"
+        INSERT INTO sessions (id, user_id, issued_at, expires_at, revoked)
+        VALUES ($1, $2, $3, $4, false)
+        "#,
+    )
+    .bind(session_id)
+    .bind(user.id)
+    .bind(now)
+    .bind(refresh_exp)
+    .execute(pool)
+    .await
+    .context("创建会话失败")?;
+
+    let access_exp = now + chrono::Duration::minutes(settings.session_minutes);
+    let access_claims = Claims {
+        sub: user.id,
+        sid: session_id,
+        role: Role::parse(&user.role).as_str().to_string(),
+        kind: TokenKind::Access,
+        iat: now.timestamp() as usize,
+        exp: access_exp.timestamp() as usize,
+    };
+    let refresh_claims = Claims {
+        kind: TokenKind::Refresh,
+        exp: refresh_exp.timestamp() as usize,
+        ..access_claims.clone()
+    };
+
+    Ok(TokenPair {
+        access_token: sign(&access_claims, settings)?,
+        access_expires_at: access_exp.timestamp(),
+        refresh_token: sign(&refresh_claims, settings)?,
+    })
+}
+
+/// `refresh_access_token` 的失败原因：区分令牌本身无效/会话失效（客户端应得到 401）
+/// 与数据库等内部故障（客户端应得到 500），避免把临时的基础设施问题误判成凭证问题。
+pub enum RefreshError {
+    Unauthorized(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+/// 用有效且未被吊销的刷新令牌兑换一个新的访问令牌，不轮换刷新令牌本身。
+pub async fn refresh_access_token(
+    pool: &Pool<Postgres>,
+    refresh_token: &str,
+    settings: &AuthSettings,
+) -> Result<(String, i64), RefreshError> {
+    let claims =
+        decode_claims(refresh_token, settings).map_err(RefreshError::Unauthorized)?;
+    if claims.kind != TokenKind::Refresh {
+        return Err(RefreshError::Unauthorized(anyhow::anyhow!(
+            "不是有效的刷新令牌"
+        )));
+    }
+
+    let active = session_is_active(pool, claims.sid)
+        .await
+        .map_err(RefreshError::Internal)?;
+    if !active {
+        return Err(RefreshError::Unauthorized(anyhow::anyhow!(
+            "会话已失效或被吊销"
+        )));
+    }
+
+    let user = load_user(pool, claims.sub)
+        .await
+        .map_err(RefreshError::Internal)?
+        .ok_or_else(|| RefreshError::Unauthorized(anyhow::anyhow!("用户不存在")))?;
+
+    let now = Utc::now();
+    let access_exp = now + chrono::Duration::minutes(settings.session_minutes);
+    let access_claims = Claims {
+        sub: user.id,
+        sid: claims.sid,
+        role: Role::parse(&user.role).as_str().to_string(),
+        kind: TokenKind::Access,
+        iat: now.timestamp() as usize,
+        exp: access_exp.timestamp() as usize,
+    };
+
+    let access_token = sign(&access_claims, settings).map_err(RefreshError::Internal)?;
+    Ok((access_token, access_exp.timestamp()))
+}
+
+/// 吊销一个会话，使其签发的访问/刷新令牌立即失效（无论 `exp` 是否已到）。
+pub async fn revoke_session(pool: &Pool<Postgres>, session_id: Uuid) -> Result<()> {
+    sqlx::query(r#"UPDATE sessions SET revoked = true WHERE id = $1"#)
+        .bind(session_id)
+        .execute(pool)
+        .await
+        .context("吊销会话失败")?;
+    Ok(())
+}
+
+async fn session_is_active(pool: &Pool<Postgres>, session_id: Uuid) -> Result<bool> {
+    let row: Option<(bool, DateTime<Utc>)> = sqlx::query_as(
+        r#"SELECT revoked, expires_at FROM sessions WHERE id = $1"#,
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .context("查询会话状态失败")?;
+
+    Ok(match row {
+        Some((revoked, expires_at)) => !revoked && expires_at > Utc::now(),
+        None => false,
+    })
+}
+
+fn decode_claims(token: &str, settings: &AuthSettings) -> Result<Claims> {
+    let validation = Validation::default();
+    let data = decode::<Claims>(token, &settings.decoding, &validation)?;
+    Ok(data.claims)
+}
+
+pub async fn require_auth(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, axum::http::StatusCode> {
+    let token = extract_token(&req).ok_or(axum::http::StatusCode::UNAUTHORIZED)?;
+
+    let claims =
+        decode_claims(token, &state.auth).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
+    if claims.kind != TokenKind::Access {
+        return Err(axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    let active = session_is_active(&state.pool, claims.sid)
+        .await
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !active {
+        return Err(axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    let user = load_user(&state.pool, claims.sub)
+        .await
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(axum::http::StatusCode::UNAUTHORIZED)?;
+
+    req.extensions_mut().insert(AuthUser {
+        id: user.id,
+        username: user.username,
+        role: Role::parse(&user.role),
+        group_tag: user.group_tag,
+        session_id: claims.sid,
+    });
+    Ok(next.run(req).await)
+}
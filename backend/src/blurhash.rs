@@ -0,0 +1,120 @@
+//! Minimal BlurHash encoder (https://blurha.sh), following the reference algorithm:
+//! decode to linear RGB, project onto a small DCT-like basis, quantize the DC/AC
+//! terms and pack them into the standard base-83 string.
+
+use anyhow::Result;
+
+const BASE83_CHARS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+pub fn encode(x_components: u32, y_components: u32, width: u32, height: u32, rgb: &[u8]) -> Result<String> {
+    if !(1..=9).contains(&x_components) || !(1..=9).contains(&y_components) {
+        anyhow::bail!("blurhash 分量数量必须在 1..=9 之间");
+    }
+    if (rgb.len() as u64) < width as u64 * height as u64 * 3 {
+        anyhow::bail!("像素缓冲区大小与给定的宽高不匹配");
+    }
+
+    let mut factors = Vec::with_capacity((x_components * y_components) as usize);
+    for cy in 0..y_components {
+        for cx in 0..x_components {
+            factors.push(basis_average(rgb, width, height, cx, cy));
+        }
+    }
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    let size_flag = (x_components - 1) + (y_components - 1) * 9;
+    let mut result = base83_encode(size_flag as u64, 1);
+
+    let max_value = if ac.is_empty() {
+        result.push_str(&base83_encode(0, 1));
+        1.0
+    } else {
+        let actual_max = ac
+            .iter()
+            .flat_map(|&(r, g, b)| [r, g, b])
+            .fold(0.0f64, |acc, v| acc.max(v.abs()));
+        let quantised = ((actual_max * 166.0 - 0.5).floor() as i64).clamp(0, 82);
+        result.push_str(&base83_encode(quantised as u64, 1));
+        (quantised as f64 + 1.0) / 166.0
+    };
+
+    result.push_str(&base83_encode(encode_dc(dc), 4));
+    for &(r, g, b) in ac {
+        result.push_str(&base83_encode(encode_ac(r, g, b, max_value), 2));
+    }
+
+    Ok(result)
+}
+
+fn basis_average(rgb: &[u8], width: u32, height: u32, cx: u32, cy: u32) -> (f64, f64, f64) {
+    let normalisation = if cx == 0 && cy == 0 { 1.0 } else { 2.0 };
+    let mut r = 0.0f64;
+    let mut g = 0.0f64;
+    let mut b = 0.0f64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let basis = normalisation
+                * (std::f64::consts::PI * cx as f64 * x as f64 / width as f64).cos()
+                * (std::f64::consts::PI * cy as f64 * y as f64 / height as f64).cos();
+            let idx = ((y * width + x) * 3) as usize;
+            r += basis * srgb_to_linear(rgb[idx]);
+            g += basis * srgb_to_linear(rgb[idx + 1]);
+            b += basis * srgb_to_linear(rgb[idx + 2]);
+        }
+    }
+
+    let scale = 1.0 / (width as f64 * height as f64);
+    (r * scale, g * scale, b * scale)
+}
+
+fn encode_dc(value: (f64, f64, f64)) -> u64 {
+    let r = linear_to_srgb(value.0) as u64;
+    let g = linear_to_srgb(value.1) as u64;
+    let b = linear_to_srgb(value.2) as u64;
+    (r << 16) + (g << 8) + b
+}
+
+fn encode_ac(r: f64, g: f64, b: f64, max_value: f64) -> u64 {
+    let quantise = |value: f64| -> u64 {
+        let normalised = sign_pow(value / max_value, 0.5);
+        ((normalised * 9.0 + 9.5).floor() as i64).clamp(0, 18) as u64
+    };
+    quantise(r) * 19 * 19 + quantise(g) * 19 + quantise(b)
+}
+
+fn sign_pow(value: f64, exp: f64) -> f64 {
+    value.abs().powf(exp) * value.signum()
+}
+
+fn srgb_to_linear(value: u8) -> f64 {
+    let v = value as f64 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let s = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0 + 0.5).floor().clamp(0.0, 255.0) as u8
+}
+
+fn base83_encode(value: u64, length: usize) -> String {
+    let mut digits = vec![0u8; length];
+    let mut value = value;
+    for slot in digits.iter_mut().rev() {
+        *slot = BASE83_CHARS[(value % 83) as usize];
+        value /= 83;
+    }
+    String::from_utf8(digits).expect("base83 字符集均为 ASCII")
+}
@@ -0,0 +1,215 @@
+use std::time::SystemTime;
+
+use axum::{
+    body::Body,
+    http::{
+        header::{
+            ACCEPT_RANGES, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+            IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+        },
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+};
+use tokio_util::io::ReaderStream;
+
+use crate::storage::CaptureStorage;
+
+const CACHE_CONTROL_VALUE: &str = "private, max-age=3600";
+
+enum RangeRequest {
+    Ignore,
+    Unsatisfiable,
+    Satisfiable(u64, u64),
+}
+
+fn parse_range(header: &str, len: u64) -> RangeRequest {
+    let Some(spec) = header.strip_prefix("bytes=") else {
+        return RangeRequest::Ignore;
+    };
+    // Only a single byte-range-spec is supported; multi-range requests fall back to a full response.
+    if spec.contains(',') {
+        return RangeRequest::Ignore;
+    }
+    let Some((start_s, end_s)) = spec.split_once('-') else {
+        return RangeRequest::Ignore;
+    };
+
+    if len == 0 {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    if start_s.is_empty() {
+        // Suffix range: last N bytes.
+        let Ok(suffix_len) = end_s.parse::<u64>() else {
+            return RangeRequest::Ignore;
+        };
+        if suffix_len == 0 {
+            return RangeRequest::Unsatisfiable;
+        }
+        let suffix_len = suffix_len.min(len);
+        return RangeRequest::Satisfiable(len - suffix_len, len - 1);
+    }
+
+    let Ok(start) = start_s.parse::<u64>() else {
+        return RangeRequest::Ignore;
+    };
+    if start >= len {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    let end = if end_s.is_empty() {
+        len - 1
+    } else {
+        match end_s.parse::<u64>() {
+            Ok(end) => end.min(len - 1),
+            Err(_) => return RangeRequest::Ignore,
+        }
+    };
+
+    if end < start {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Satisfiable(start, end)
+}
+
+/// 强 ETag：由 mtime(纳秒) + 文件长度派生，同一份内容在未改动的情况下始终得到
+/// 相同的值。206 Range 响应只允许使用强校验器验证(RFC 7232 §2.1)，因此这里不
+/// 能加 `W/` 弱前缀。
+fn etag_for(len: u64, modified: SystemTime) -> String {
+    let nanos = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("\"{:x}-{:x}\"", nanos, len)
+}
+
+fn not_modified(etag: &str, last_modified: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(ETAG, etag)
+        .header(LAST_MODIFIED, last_modified)
+        .header(CACHE_CONTROL, CACHE_CONTROL_VALUE)
+        .body(Body::empty())
+        .expect("valid 304 response")
+}
+
+/// `If-Range` 决定一个带 `Range` 头的请求是否仍可返回 206：校验器（ETag 或
+/// `Last-Modified`）与当前表示不匹配时，说明客户端手里的分片已经过期，必须退回
+/// 完整的 200 响应，否则会把新旧内容的字节拼接在一起。没有 `If-Range` 头时按
+/// 规范直接放行 Range 请求。
+fn range_is_fresh(headers: &HeaderMap, etag: &str, modified: SystemTime) -> bool {
+    let Some(if_range) = headers.get(IF_RANGE).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return if_range == etag;
+    }
+
+    match httpdate::parse_http_date(if_range) {
+        Ok(since) => modified <= since,
+        Err(_) => false,
+    }
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: SystemTime) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match
+            .split(',')
+            .any(|candidate| candidate.trim() == etag || candidate.trim() == "*");
+    }
+
+    if let Some(if_modified_since) = headers.get(IF_MODIFIED_SINCE).and_then(|v| v.to_str().ok())
+    {
+        if let Ok(since) = httpdate::parse_http_date(if_modified_since) {
+            return modified <= since;
+        }
+    }
+
+    false
+}
+
+/// 通过捕获存储后端读取一帧，支持 Range 请求与条件 GET。`frame_path` 是数据库中
+/// 记录的逻辑路径；具体解析（本地文件系统或对象存储 key）交由 `storage` 负责。
+pub async fn serve_capture_file(
+    headers: &HeaderMap,
+    storage: &dyn CaptureStorage,
+    frame_path: &str,
+) -> Result<Response, ApiFileError> {
+    let stat = storage
+        .stat(frame_path)
+        .await
+        .map_err(|_| ApiFileError::NotFound)?;
+    let len = stat.len;
+    let modified = stat.modified;
+    let etag = etag_for(len, modified);
+    let last_modified = httpdate::fmt_http_date(modified);
+
+    if is_not_modified(headers, &etag, modified) {
+        return Ok(not_modified(&etag, &last_modified));
+    }
+
+    let content_type = storage.content_type(frame_path);
+
+    let range_header = headers
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| range_is_fresh(headers, &etag, modified));
+
+    if let Some(range) = range_header {
+        match parse_range(range, len) {
+            RangeRequest::Unsatisfiable => {
+                return Ok(Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(CONTENT_RANGE, format!("bytes */{}", len))
+                    .body(Body::empty())
+                    .expect("valid 416 response"));
+            }
+            RangeRequest::Satisfiable(start, end) => {
+                let reader = storage
+                    .open_range(frame_path, start, end)
+                    .await
+                    .map_err(ApiFileError::Io)?;
+                let chunk_len = end - start + 1;
+                let stream = ReaderStream::new(reader);
+
+                return Ok(Response::builder()
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(CONTENT_TYPE, content_type)
+                    .header(CONTENT_LENGTH, chunk_len)
+                    .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                    .header(ACCEPT_RANGES, "bytes")
+                    .header(ETAG, etag)
+                    .header(LAST_MODIFIED, last_modified)
+                    .header(CACHE_CONTROL, CACHE_CONTROL_VALUE)
+                    .body(Body::from_stream(stream))
+                    .expect("valid 206 response"));
+            }
+            RangeRequest::Ignore => {}
+        }
+    }
+
+    let reader = storage
+        .open_range(frame_path, 0, len.saturating_sub(1))
+        .await
+        .map_err(ApiFileError::Io)?;
+    let stream = ReaderStream::new(reader);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, len)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ETAG, etag)
+        .header(LAST_MODIFIED, last_modified)
+        .header(CACHE_CONTROL, CACHE_CONTROL_VALUE)
+        .body(Body::from_stream(stream))
+        .expect("valid 200 response"))
+}
+
+pub enum ApiFileError {
+    NotFound,
+    Io(anyhow::Error),
+}
@@ -2,48 +2,66 @@ use std::{
     fs::OpenOptions,
     net::SocketAddr,
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::{Arc, OnceLock},
 };
 
 use anyhow::{Context, Result};
 use axum::{
     body::Body,
-    extract::{Path as AxumPath, Query, State},
-    http::{header::AUTHORIZATION, Request, StatusCode},
-    middleware::{self, Next},
+    extract::{Extension, Path as AxumPath, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, Request, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use chrono::{DateTime, FixedOffset, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPoolOptions, FromRow, Pool, Postgres, Row};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    compression::{
+        predicate::{And, NotForContentType, Predicate, SizeAbove},
+        CompressionLayer,
+    },
+    cors::CorsLayer,
+    trace::TraceLayer,
+};
 use tracing_subscriber::{fmt, layer::SubscriberExt, prelude::*, util::SubscriberInitExt};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
+mod auth;
+mod blurhash;
+mod capture;
+mod openapi;
+mod storage;
+mod thumbnail;
+
+use auth::{AuthSettings, AuthUser, Role};
+use openapi::ApiDoc;
+use storage::CaptureStorage;
+
 static FILE_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
 
+/// 缩略图默认最大边长；`?size=` 可覆盖，但会被夹取到 `THUMBNAIL_MIN_DIM..=THUMBNAIL_MAX_DIM`。
+const THUMBNAIL_DEFAULT_DIM: u32 = 320;
+const THUMBNAIL_MIN_DIM: u32 = 32;
+const THUMBNAIL_MAX_DIM: u32 = 1024;
+
 #[derive(Clone)]
 struct AppState {
     pool: Pool<Postgres>,
-    capture_root: Option<PathBuf>,
+    capture_storage: Option<Arc<dyn CaptureStorage>>,
+    thumbnail_cache_root: PathBuf,
     auth: AuthSettings,
 }
 
-#[derive(Clone)]
-struct AuthSettings {
-    username: String,
-    password: String,
-    session_minutes: i64,
-    encoding: EncodingKey,
-    decoding: DecodingKey,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 struct ListParams {
+    /// 返回记录数上限，超出范围会被夹取到 1..=200
     limit: Option<i64>,
+    /// 按分组标签过滤
     group_tag: Option<String>,
 }
 
@@ -55,9 +73,10 @@ struct FaceCaptureRow {
     frame_path: Option<String>,
     face_distance: Option<f64>,
     timestamp: DateTime<FixedOffset>,
+    blurhash: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct FaceCapture {
     id: Uuid,
     identity: String,
@@ -65,11 +84,22 @@ struct FaceCapture {
     face_distance: Option<f64>,
     timestamp: DateTime<FixedOffset>,
     image_url: Option<String>,
+    thumb_url: Option<String>,
+    /// BlurHash 占位字符串，供前端在图片加载完成前渲染模糊预览
+    blurhash: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+struct ThumbnailQuery {
+    /// 缩略图最大边长（像素），超出范围会被夹取到 32..=1024，默认 320
+    size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
 struct PostureListParams {
+    /// 返回记录数上限，超出范围会被夹取到 1..=200
     limit: Option<i64>,
+    /// 仅返回坐姿不良(true)或正常(false)的记录
     is_bad: Option<bool>,
 }
 
@@ -85,9 +115,14 @@ struct PostureRow {
     frame_path: Option<String>,
     face_capture_id: Option<Uuid>,
     timestamp: DateTime<FixedOffset>,
+    blurhash: Option<String>,
+    /// 关联人脸抓拍记录的帧路径，仅用于在未持久化 BlurHash 时确定该补齐哪一帧。
+    face_frame_path: Option<String>,
+    /// 关联人脸抓拍记录已持久化的 BlurHash，`image_url`/`thumb_url` 指向该帧时应以此为准。
+    face_blurhash: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct PostureEvent {
     id: Uuid,
     identity: String,
@@ -104,31 +139,39 @@ struct PostureEvent {
     face_capture_id: Option<Uuid>,
     timestamp: DateTime<FixedOffset>,
     image_url: Option<String>,
+    thumb_url: Option<String>,
+    /// BlurHash 占位字符串，供前端在图片加载完成前渲染模糊预览
+    blurhash: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ApiErrorBody {
     message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct LoginRequest {
     username: String,
     password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct LoginResponse {
     token: String,
     expires_at: i64,
     username: String,
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct RefreshRequest {
+    refresh_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Claims {
-    sub: String,
-    exp: usize,
-    iat: usize,
+#[derive(Debug, Serialize, ToSchema)]
+struct RefreshResponse {
+    token: String,
+    expires_at: i64,
 }
 
 struct ApiError(anyhow::Error, StatusCode);
@@ -165,31 +208,59 @@ async fn main() -> Result<()> {
         .await
         .context("无法连接数据库，请检查 DSN/网络")?;
 
-    let capture_root = capture_root(config.as_ref());
+    let capture_storage = build_capture_storage(config.as_ref())
+        .await
+        .context("初始化捕获存储后端失败")?;
 
-    let auth = build_auth_settings(config.as_ref());
+    let auth_config = config.as_ref().and_then(|c| c.auth.as_ref());
+    auth::bootstrap_admin(&pool, auth_config).await?;
+    let auth = auth::build_auth_settings(auth_config);
 
     let state = AppState {
         pool,
-        capture_root,
+        capture_storage,
+        thumbnail_cache_root: repo_root().join("data/thumbnails"),
         auth,
     };
 
-    let protected_routes = Router::new()
+    let protected_json_routes = Router::new()
+        .route("/api/logout", post(logout))
         .route("/api/face-captures", get(list_face_captures))
-        .route("/api/face-captures/:id/image", get(get_face_capture_image))
         .route("/api/posture-events", get(list_posture_events))
+        .with_state(state.clone())
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+
+    // 抓拍图片/缩略图走独立的 Router，完全绕开压缩层：不能靠 Content-Type 前缀
+    // 判断是否该压缩，因为未知扩展名的帧会被 CaptureStorage::content_type 归为
+    // application/octet-stream，既压缩不到位也会让 206 响应的 Content-Range 与
+    // 实际（被压缩后的）字节错位。
+    let protected_image_routes = Router::new()
+        .route("/api/face-captures/:id/image", get(get_face_capture_image))
+        .route("/api/face-captures/:id/thumb", get(get_face_capture_thumb))
         .route(
             "/api/posture-events/:id/image",
             get(get_posture_event_image),
         )
+        .route("/api/posture-events/:id/thumb", get(get_posture_event_thumb))
         .with_state(state.clone())
-        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
 
-    let app = Router::new()
+    let json_app = Router::new()
         .route("/api/login", post(login))
-        .merge(protected_routes)
+        .route("/api/refresh", post(refresh))
+        .merge(protected_json_routes)
         .with_state(state)
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()))
+        .layer(build_compression_layer(config.as_ref()));
+
+    let app = json_app
+        .merge(protected_image_routes)
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http());
 
@@ -219,59 +290,120 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "登录成功，返回访问令牌", body = LoginResponse),
+        (status = 401, description = "用户名或密码错误", body = ApiErrorBody),
+    )
+)]
 async fn login(
     State(state): State<AppState>,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, ApiError> {
-    if payload.username != state.auth.username || payload.password != state.auth.password {
-        return Err(ApiError(
-            anyhow::anyhow!("用户名或密码错误"),
-            StatusCode::UNAUTHORIZED,
-        ));
-    }
-
-    let now = Utc::now();
-    let exp = now + chrono::Duration::minutes(state.auth.session_minutes.max(1));
-    let claims = Claims {
-        sub: payload.username.clone(),
-        iat: now.timestamp() as usize,
-        exp: exp.timestamp() as usize,
-    };
-
-    let token = encode(&Header::default(), &claims, &state.auth.encoding)
-        .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?;
+    let result = auth::login(&state.pool, &payload.username, &payload.password, &state.auth)
+        .await
+        .map_err(|err| ApiError(err, StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| {
+            ApiError(
+                anyhow::anyhow!("用户名或密码错误"),
+                StatusCode::UNAUTHORIZED,
+            )
+        })?;
 
     Ok(Json(LoginResponse {
-        token,
-        expires_at: exp.timestamp(),
-        username: payload.username,
+        token: result.tokens.access_token,
+        expires_at: result.tokens.access_expires_at,
+        username: result.username,
+        refresh_token: result.tokens.refresh_token,
     }))
 }
 
-async fn require_auth(
+#[utoipa::path(
+    post,
+    path = "/api/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "刷新成功，返回新的访问令牌", body = RefreshResponse),
+        (status = 401, description = "刷新令牌无效、已过期或会话已被吊销", body = ApiErrorBody),
+    )
+)]
+async fn refresh(
     State(state): State<AppState>,
-    mut req: Request<Body>,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    let token = extract_token(&req).ok_or(StatusCode::UNAUTHORIZED)?;
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<RefreshResponse>, ApiError> {
+    let (token, expires_at) =
+        auth::refresh_access_token(&state.pool, &payload.refresh_token, &state.auth)
+            .await
+            .map_err(|err| match err {
+                auth::RefreshError::Unauthorized(err) => ApiError(err, StatusCode::UNAUTHORIZED),
+                auth::RefreshError::Internal(err) => {
+                    ApiError(err, StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            })?;
 
-    let claims = validate_token(token, &state.auth).map_err(|_| StatusCode::UNAUTHORIZED)?;
-    req.extensions_mut().insert(claims);
-    Ok(next.run(req).await)
+    Ok(Json(RefreshResponse { token, expires_at }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "已登出，当前会话立即失效"),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn logout(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<StatusCode, ApiError> {
+    auth::revoke_session(&state.pool, user.session_id)
+        .await
+        .map_err(|err| ApiError(err, StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/face-captures",
+    tag = "face-captures",
+    params(ListParams),
+    responses(
+        (status = 200, description = "人脸抓拍记录列表，按时间倒序排列", body = [FaceCapture]),
+        (status = 500, description = "数据库查询失败", body = ApiErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn list_face_captures(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
     Query(params): Query<ListParams>,
 ) -> Result<Json<Vec<FaceCapture>>, ApiError> {
     let limit = params.limit.unwrap_or(40).clamp(1, 200);
-    let group_tag = params
+    let requested_tag = params
         .group_tag
         .as_deref()
         .map(str::trim)
         .filter(|value| !value.is_empty())
         .map(|value| value.to_string());
 
+    // viewer 角色只能看到自己所属分组的抓拍记录，其余角色可按需过滤；
+    // 未绑定分组的 viewer 无法归属到任何分组，必须返回空列表，而不是退化为不过滤。
+    let group_tag = if user.role == Role::Viewer {
+        match user.group_tag.clone() {
+            Some(tag) => Some(tag),
+            None => return Ok(Json(Vec::new())),
+        }
+    } else {
+        requested_tag
+    };
+
     if let Some(tag) = group_tag.as_deref() {
         tracing::info!("GET /api/face-captures?limit={}&group_tag={}", limit, tag);
     } else {
@@ -287,7 +419,8 @@ async fn list_face_captures(
                 group_tag,
                 frame_path,
                 face_distance,
-                timestamp
+                timestamp,
+                blurhash
             FROM face_captures
             WHERE group_tag = $1
             ORDER BY timestamp DESC
@@ -307,7 +440,8 @@ async fn list_face_captures(
                 group_tag,
                 frame_path,
                 face_distance,
-                timestamp
+                timestamp,
+                blurhash
             FROM face_captures
             ORDER BY timestamp DESC
             LIMIT $1
@@ -319,198 +453,424 @@ async fn list_face_captures(
     }
     .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?;
 
-    let data = rows
-        .into_iter()
-        .map(|row| {
-            let image_url = row
-                .frame_path
-                .as_ref()
-                .map(|_| format!("/api/face-captures/{}/image", row.id));
-            FaceCapture {
-                id: row.id,
-                identity: row.identity,
-                group_tag: row.group_tag,
-                face_distance: row.face_distance,
-                timestamp: row.timestamp,
-                image_url,
+    let mut data = Vec::with_capacity(rows.len());
+    let mut backfill_jobs = Vec::new();
+    for row in rows {
+        let image_url = row
+            .frame_path
+            .as_ref()
+            .map(|_| format!("/api/face-captures/{}/image", row.id));
+        let thumb_url = row
+            .frame_path
+            .as_ref()
+            .map(|_| format!("/api/face-captures/{}/thumb", row.id));
+
+        if row.blurhash.is_none() {
+            if let Some(frame_path) = row.frame_path.clone() {
+                backfill_jobs.push(BlurhashBackfillJob {
+                    table: "face_captures",
+                    id: row.id,
+                    frame_path,
+                });
             }
-        })
-        .collect();
+        }
+
+        data.push(FaceCapture {
+            id: row.id,
+            identity: row.identity,
+            group_tag: row.group_tag,
+            face_distance: row.face_distance,
+            timestamp: row.timestamp,
+            image_url,
+            thumb_url,
+            blurhash: row.blurhash,
+        });
+    }
+
+    spawn_blurhash_backfill(state.pool.clone(), state.capture_storage.clone(), backfill_jobs);
 
     Ok(Json(data))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/face-captures/{id}/image",
+    tag = "face-captures",
+    params(("id" = Uuid, Path, description = "人脸抓拍记录 ID")),
+    responses(
+        (status = 200, description = "抓拍帧图片的二进制内容", content_type = "application/octet-stream"),
+        (status = 206, description = "按 Range 请求头返回的部分内容", content_type = "application/octet-stream"),
+        (status = 304, description = "协商缓存命中，内容未变化"),
+        (status = 404, description = "记录不存在或缺少帧文件", body = ApiErrorBody),
+        (status = 416, description = "Range 请求超出文件范围"),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_face_capture_image(
     AxumPath(id): AxumPath<Uuid>,
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
 ) -> Result<Response, ApiError> {
     tracing::info!("GET /api/face-captures/{}/image", id);
-    let capture_root = state.capture_root.clone().ok_or_else(|| {
-        ApiError(
-            anyhow::anyhow!("capture root not configured"),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        )
-    })?;
-
-    let row = sqlx::query(r#"SELECT frame_path FROM face_captures WHERE id = $1"#)
-        .bind(id)
-        .fetch_optional(&state.pool)
-        .await
-        .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?
-        .ok_or_else(|| {
-            ApiError(
-                anyhow::anyhow!("face capture not found"),
-                StatusCode::NOT_FOUND,
-            )
-        })?;
-
-    let frame_path: Option<String> = row.try_get("frame_path").map_err(|err| {
+    let storage = state.capture_storage.clone().ok_or_else(|| {
         ApiError(
-            anyhow::anyhow!("invalid frame_path data: {}", err),
+            anyhow::anyhow!("capture storage not configured"),
             StatusCode::INTERNAL_SERVER_ERROR,
         )
     })?;
 
-    let frame_path = frame_path.ok_or_else(|| {
-        ApiError(
-            anyhow::anyhow!("face capture has no associated frame path"),
-            StatusCode::NOT_FOUND,
-        )
-    })?;
-
-    let target_path = sanitize_capture_path(&capture_root, Path::new(&frame_path))
-        .map_err(|err| ApiError(err, StatusCode::BAD_REQUEST))?;
+    let access = fetch_face_capture_access(&state.pool, id).await?;
+    authorize_group(&user, access.group_tag.as_deref())?;
 
-    let data = tokio::fs::read(&target_path)
+    capture::serve_capture_file(&headers, storage.as_ref(), &access.frame_path)
         .await
-        .map_err(|err| ApiError(err.into(), StatusCode::NOT_FOUND))?;
-
-    let content_type = mime_guess::from_path(&target_path)
-        .first_or_octet_stream()
-        .to_string();
+        .map_err(|err| match err {
+            capture::ApiFileError::NotFound => ApiError(
+                anyhow::anyhow!("frame file not found on disk"),
+                StatusCode::NOT_FOUND,
+            ),
+            capture::ApiFileError::Io(err) => ApiError(err, StatusCode::INTERNAL_SERVER_ERROR),
+        })
+}
 
-    Ok(([(axum::http::header::CONTENT_TYPE, content_type)], data).into_response())
+#[utoipa::path(
+    get,
+    path = "/api/face-captures/{id}/thumb",
+    tag = "face-captures",
+    params(
+        ("id" = Uuid, Path, description = "人脸抓拍记录 ID"),
+        ThumbnailQuery,
+    ),
+    responses(
+        (status = 200, description = "缩略图 JPEG 二进制内容", content_type = "image/jpeg"),
+        (status = 404, description = "记录不存在或缺少帧文件", body = ApiErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_face_capture_thumb(
+    AxumPath(id): AxumPath<Uuid>,
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ThumbnailQuery>,
+) -> Result<Response, ApiError> {
+    let access = fetch_face_capture_access(&state.pool, id).await?;
+    authorize_group(&user, access.group_tag.as_deref())?;
+    serve_thumbnail(&state, "face-captures", id, &access.frame_path, params.size).await
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/posture-events",
+    tag = "posture-events",
+    params(PostureListParams),
+    responses(
+        (status = 200, description = "坐姿事件列表，按时间倒序排列", body = [PostureEvent]),
+        (status = 500, description = "数据库查询失败", body = ApiErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn list_posture_events(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
     Query(params): Query<PostureListParams>,
 ) -> Result<Json<Vec<PostureEvent>>, ApiError> {
     let limit = params.limit.unwrap_or(50).clamp(1, 200);
     let is_bad = params.is_bad;
+
+    // viewer 角色只能看到自己所属分组的坐姿事件，分组通过关联的人脸抓拍记录带出；
+    // 未绑定分组的 viewer，以及没有任何可归属分组的场景，一律返回空列表而不是
+    // 退化为不过滤（否则与 list_face_captures/图片接口的权限模型不一致）。
+    let group_tag = if user.role == Role::Viewer {
+        match user.group_tag.clone() {
+            Some(tag) => Some(tag),
+            None => return Ok(Json(Vec::new())),
+        }
+    } else {
+        None
+    };
+
     tracing::info!(
         "GET /api/posture-events?limit={}&is_bad={:?}",
         limit,
         is_bad
     );
 
-    let query = if is_bad.is_some() {
-        r#"
-        SELECT
-            id,
-            identity,
-            is_bad,
-            nose_drop,
-            neck_angle,
-            reasons,
-            face_distance,
-            frame_path,
-            face_capture_id,
-            timestamp
-        FROM posture_events
-        WHERE is_bad = $2
-        ORDER BY timestamp DESC
-        LIMIT $1
-        "#
-    } else {
+    // 始终 LEFT JOIN 人脸抓拍记录：既用于按分组过滤（WHERE 对 NULL 分组天然不匹配，
+    // 等价于排除没有关联记录的事件），也用于取得 image_url 实际指向的那一帧的 BlurHash。
+    let mut sql = String::from(
         r#"
         SELECT
-            id,
-            identity,
-            is_bad,
-            nose_drop,
-            neck_angle,
-            reasons,
-            face_distance,
-            frame_path,
-            face_capture_id,
-            timestamp
-        FROM posture_events
-        ORDER BY timestamp DESC
-        LIMIT $1
-        "#
-    };
+            pe.id,
+            pe.identity,
+            pe.is_bad,
+            pe.nose_drop,
+            pe.neck_angle,
+            pe.reasons,
+            pe.face_distance,
+            pe.frame_path,
+            pe.face_capture_id,
+            pe.timestamp,
+            pe.blurhash,
+            fc.frame_path AS face_frame_path,
+            fc.blurhash AS face_blurhash
+        FROM posture_events pe
+        LEFT JOIN face_captures fc ON fc.id = pe.face_capture_id
+        "#,
+    );
 
-    let rows: Vec<PostureRow> = if let Some(flag) = is_bad {
-        sqlx::query_as(query).bind(limit).bind(flag)
-    } else {
-        sqlx::query_as(query).bind(limit)
+    let mut next_param = 2;
+    let mut conditions = Vec::new();
+    if group_tag.is_some() {
+        conditions.push(format!("fc.group_tag = ${}", next_param));
+        next_param += 1;
     }
-    .fetch_all(&state.pool)
-    .await
-    .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?;
+    if is_bad.is_some() {
+        conditions.push(format!("pe.is_bad = ${}", next_param));
+        next_param += 1;
+    }
+    if !conditions.is_empty() {
+        sql.push_str("WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+        sql.push(' ');
+    }
+    sql.push_str("ORDER BY pe.timestamp DESC LIMIT $1");
 
-    let data = rows
-        .into_iter()
-        .map(|row| {
-            let reasons = row
-                .reasons
-                .as_ref()
-                .map(|r| {
-                    r.split(',')
-                        .map(|part| part.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default();
-            // Prefer the linked face capture image when available; otherwise fall back to posture image.
-            let image_url = row
-                .face_capture_id
-                .map(|face_id| format!("/api/face-captures/{}/image", face_id))
-                .or_else(|| {
-                    row.frame_path
-                        .as_ref()
-                        .map(|_| format!("/api/posture-events/{}/image", row.id))
+    let mut query = sqlx::query_as::<_, PostureRow>(&sql).bind(limit);
+    if let Some(tag) = group_tag {
+        query = query.bind(tag);
+    }
+    if let Some(flag) = is_bad {
+        query = query.bind(flag);
+    }
+
+    let rows: Vec<PostureRow> = query
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    let mut data = Vec::with_capacity(rows.len());
+    let mut backfill_jobs = Vec::new();
+    for row in rows {
+        let reasons = row
+            .reasons
+            .as_ref()
+            .map(|r| {
+                r.split(',')
+                    .map(|part| part.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        // Prefer the linked face capture image when available; otherwise fall back to posture image.
+        let image_url = row
+            .face_capture_id
+            .map(|face_id| format!("/api/face-captures/{}/image", face_id))
+            .or_else(|| {
+                row.frame_path
+                    .as_ref()
+                    .map(|_| format!("/api/posture-events/{}/image", row.id))
+            });
+        let thumb_url = row
+            .face_capture_id
+            .map(|face_id| format!("/api/face-captures/{}/thumb", face_id))
+            .or_else(|| {
+                row.frame_path
+                    .as_ref()
+                    .map(|_| format!("/api/posture-events/{}/thumb", row.id))
+            });
+
+        // image_url/thumb_url 在关联了人脸抓拍记录时指向该记录的帧，BlurHash 必须
+        // 取自同一帧，否则占位图会和实际渲染的图片对不上。
+        let blurhash = if row.face_capture_id.is_some() {
+            row.face_blurhash.clone()
+        } else {
+            row.blurhash.clone()
+        };
+
+        if blurhash.is_none() {
+            if let Some(face_id) = row.face_capture_id {
+                if let Some(frame_path) = row.face_frame_path.clone() {
+                    backfill_jobs.push(BlurhashBackfillJob {
+                        table: "face_captures",
+                        id: face_id,
+                        frame_path,
+                    });
+                }
+            } else if let Some(frame_path) = row.frame_path.clone() {
+                backfill_jobs.push(BlurhashBackfillJob {
+                    table: "posture_events",
+                    id: row.id,
+                    frame_path,
                 });
-            PostureEvent {
-                id: row.id,
-                identity: row.identity,
-                is_bad: row.is_bad,
-                nose_drop: row.nose_drop,
-                neck_angle: row.neck_angle,
-                reasons,
-                face_distance: row.face_distance,
-                frame_path: row.frame_path,
-                face_capture_id: row.face_capture_id,
-                timestamp: row.timestamp,
-                image_url,
             }
-        })
-        .collect();
+        }
+
+        data.push(PostureEvent {
+            id: row.id,
+            identity: row.identity,
+            is_bad: row.is_bad,
+            nose_drop: row.nose_drop,
+            neck_angle: row.neck_angle,
+            reasons,
+            face_distance: row.face_distance,
+            frame_path: row.frame_path,
+            face_capture_id: row.face_capture_id,
+            timestamp: row.timestamp,
+            image_url,
+            thumb_url,
+            blurhash,
+        });
+    }
+
+    spawn_blurhash_backfill(state.pool.clone(), state.capture_storage.clone(), backfill_jobs);
 
     Ok(Json(data))
 }
 
+/// 一条待补齐的 BlurHash 任务：目标表（`face_captures` 或 `posture_events`）、记录 id
+/// 及其待解码的帧路径。
+struct BlurhashBackfillJob {
+    table: &'static str,
+    id: Uuid,
+    frame_path: String,
+}
+
+/// 缺失 BlurHash 记录的后台补齐并发度上限，避免一次列表请求触发的补齐任务
+/// 同时打满存储后端（尤其是 S3 等远程后端的连接数）。
+const BLURHASH_BACKFILL_CONCURRENCY: usize = 4;
+
+/// 对列表接口中缺失 BlurHash 的记录做有限并发的后台补齐，不阻塞当前请求，
+/// 也不在读路径上做存储读取或数据库写入；单条记录补齐失败只记录警告。
+fn spawn_blurhash_backfill(
+    pool: Pool<Postgres>,
+    storage: Option<Arc<dyn CaptureStorage>>,
+    jobs: Vec<BlurhashBackfillJob>,
+) {
+    let Some(storage) = storage else {
+        return;
+    };
+    if jobs.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut pending = jobs.into_iter();
+        let mut running = tokio::task::JoinSet::new();
+        for job in pending.by_ref().take(BLURHASH_BACKFILL_CONCURRENCY) {
+            running.spawn(backfill_blurhash(pool.clone(), storage.clone(), job));
+        }
+        while running.join_next().await.is_some() {
+            if let Some(job) = pending.next() {
+                running.spawn(backfill_blurhash(pool.clone(), storage.clone(), job));
+            }
+        }
+    });
+}
+
+async fn backfill_blurhash(pool: Pool<Postgres>, storage: Arc<dyn CaptureStorage>, job: BlurhashBackfillJob) {
+    let hash = match thumbnail::compute_blurhash(storage.as_ref(), &job.frame_path).await {
+        Ok(hash) => hash,
+        Err(err) => {
+            tracing::warn!("计算 BlurHash 失败 ({}/{}): {}", job.table, job.id, err);
+            return;
+        }
+    };
+
+    let update = format!("UPDATE {} SET blurhash = $1 WHERE id = $2", job.table);
+    if let Err(err) = sqlx::query(&update)
+        .bind(&hash)
+        .bind(job.id)
+        .execute(&pool)
+        .await
+    {
+        tracing::warn!("保存 BlurHash 失败 ({}/{}): {}", job.table, job.id, err);
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posture-events/{id}/image",
+    tag = "posture-events",
+    params(("id" = Uuid, Path, description = "坐姿事件记录 ID")),
+    responses(
+        (status = 200, description = "事件帧图片的二进制内容", content_type = "application/octet-stream"),
+        (status = 206, description = "按 Range 请求头返回的部分内容", content_type = "application/octet-stream"),
+        (status = 304, description = "协商缓存命中，内容未变化"),
+        (status = 404, description = "记录不存在或缺少帧文件", body = ApiErrorBody),
+        (status = 416, description = "Range 请求超出文件范围"),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_posture_event_image(
     AxumPath(id): AxumPath<Uuid>,
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
 ) -> Result<Response, ApiError> {
     tracing::info!("GET /api/posture-events/{}/image", id);
-    let capture_root = state.capture_root.clone().ok_or_else(|| {
+    let storage = state.capture_storage.clone().ok_or_else(|| {
         ApiError(
-            anyhow::anyhow!("capture root not configured"),
+            anyhow::anyhow!("capture storage not configured"),
             StatusCode::INTERNAL_SERVER_ERROR,
         )
     })?;
 
-    let row = sqlx::query(r#"SELECT frame_path FROM posture_events WHERE id = $1"#)
+    let access = fetch_posture_event_access(&state.pool, id).await?;
+    authorize_group(&user, access.group_tag.as_deref())?;
+
+    capture::serve_capture_file(&headers, storage.as_ref(), &access.frame_path)
+        .await
+        .map_err(|err| match err {
+            capture::ApiFileError::NotFound => ApiError(
+                anyhow::anyhow!("frame file not found on disk"),
+                StatusCode::NOT_FOUND,
+            ),
+            capture::ApiFileError::Io(err) => ApiError(err, StatusCode::INTERNAL_SERVER_ERROR),
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posture-events/{id}/thumb",
+    tag = "posture-events",
+    params(
+        ("id" = Uuid, Path, description = "坐姿事件记录 ID"),
+        ThumbnailQuery,
+    ),
+    responses(
+        (status = 200, description = "缩略图 JPEG 二进制内容", content_type = "image/jpeg"),
+        (status = 404, description = "记录不存在或缺少帧文件", body = ApiErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_posture_event_thumb(
+    AxumPath(id): AxumPath<Uuid>,
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ThumbnailQuery>,
+) -> Result<Response, ApiError> {
+    let access = fetch_posture_event_access(&state.pool, id).await?;
+    authorize_group(&user, access.group_tag.as_deref())?;
+    serve_thumbnail(&state, "posture-events", id, &access.frame_path, params.size).await
+}
+
+/// 一条记录的帧路径及其归属分组，供鉴权与取图共用。
+struct CaptureAccess {
+    frame_path: String,
+    group_tag: Option<String>,
+}
+
+/// 读取指定人脸抓拍记录的帧路径及其所属分组。
+async fn fetch_face_capture_access(pool: &Pool<Postgres>, id: Uuid) -> Result<CaptureAccess, ApiError> {
+    let row = sqlx::query(r#"SELECT frame_path, group_tag FROM face_captures WHERE id = $1"#)
         .bind(id)
-        .fetch_optional(&state.pool)
+        .fetch_optional(pool)
         .await
         .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?
         .ok_or_else(|| {
             ApiError(
-                anyhow::anyhow!("posture event not found"),
+                anyhow::anyhow!("face capture not found"),
                 StatusCode::NOT_FOUND,
             )
         })?;
@@ -521,6 +881,60 @@ async fn get_posture_event_image(
             StatusCode::INTERNAL_SERVER_ERROR,
         )
     })?;
+    let group_tag: String = row.try_get("group_tag").map_err(|err| {
+        ApiError(
+            anyhow::anyhow!("invalid group_tag data: {}", err),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    })?;
+
+    let frame_path = frame_path.ok_or_else(|| {
+        ApiError(
+            anyhow::anyhow!("face capture has no associated frame path"),
+            StatusCode::NOT_FOUND,
+        )
+    })?;
+
+    Ok(CaptureAccess {
+        frame_path,
+        group_tag: Some(group_tag),
+    })
+}
+
+/// 读取指定坐姿事件记录的帧路径；分组通过其关联的人脸抓拍记录(若有)带出，
+/// 未关联任何人脸抓拍的事件没有可归属的分组。
+async fn fetch_posture_event_access(pool: &Pool<Postgres>, id: Uuid) -> Result<CaptureAccess, ApiError> {
+    let row = sqlx::query(
+        r#"
+        SELECT pe.frame_path AS frame_path, fc.group_tag AS group_tag
+        FROM posture_events pe
+        LEFT JOIN face_captures fc ON fc.id = pe.face_capture_id
+        WHERE pe.id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|err| ApiError(err.into(), StatusCode::INTERNAL_SERVER_ERROR))?
+    .ok_or_else(|| {
+        ApiError(
+            anyhow::anyhow!("posture event not found"),
+            StatusCode::NOT_FOUND,
+        )
+    })?;
+
+    let frame_path: Option<String> = row.try_get("frame_path").map_err(|err| {
+        ApiError(
+            anyhow::anyhow!("invalid frame_path data: {}", err),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    })?;
+    let group_tag: Option<String> = row.try_get("group_tag").map_err(|err| {
+        ApiError(
+            anyhow::anyhow!("invalid group_tag data: {}", err),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    })?;
 
     let frame_path = frame_path.ok_or_else(|| {
         ApiError(
@@ -529,18 +943,81 @@ async fn get_posture_event_image(
         )
     })?;
 
-    let target_path = sanitize_capture_path(&capture_root, Path::new(&frame_path))
-        .map_err(|err| ApiError(err, StatusCode::BAD_REQUEST))?;
+    Ok(CaptureAccess {
+        frame_path,
+        group_tag,
+    })
+}
 
-    let data = tokio::fs::read(&target_path)
-        .await
-        .map_err(|err| ApiError(err.into(), StatusCode::NOT_FOUND))?;
+/// viewer 只能访问自己所属分组的记录；记录没有可归属的分组(`group_tag` 为
+/// `None`)时一律视为无权限。其余角色不受限制。返回与"记录不存在"相同的
+/// 404，避免向无权限的调用方暴露记录是否存在。
+fn authorize_group(user: &AuthUser, group_tag: Option<&str>) -> Result<(), ApiError> {
+    if user.role != Role::Viewer {
+        return Ok(());
+    }
+
+    let authorized = matches!(
+        (user.group_tag.as_deref(), group_tag),
+        (Some(user_tag), Some(row_tag)) if user_tag == row_tag
+    );
+
+    if authorized {
+        Ok(())
+    } else {
+        Err(ApiError(
+            anyhow::anyhow!("record not found"),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
 
-    let content_type = mime_guess::from_path(&target_path)
-        .first_or_octet_stream()
-        .to_string();
+async fn serve_thumbnail(
+    state: &AppState,
+    kind: &str,
+    id: Uuid,
+    frame_path: &str,
+    requested_size: Option<u32>,
+) -> Result<Response, ApiError> {
+    let storage = state.capture_storage.clone().ok_or_else(|| {
+        ApiError(
+            anyhow::anyhow!("capture storage not configured"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    })?;
+
+    let max_dim = requested_size
+        .unwrap_or(THUMBNAIL_DEFAULT_DIM)
+        .clamp(THUMBNAIL_MIN_DIM, THUMBNAIL_MAX_DIM);
+
+    let bytes = match thumbnail::thumbnail(
+        storage.as_ref(),
+        frame_path,
+        &state.thumbnail_cache_root,
+        kind,
+        id,
+        max_dim,
+    )
+    .await
+    {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            // 与原图接口一致：帧文件在存储中不存在时返回 404，其余失败返回 500。
+            let status = if storage.stat(frame_path).await.is_err() {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            return Err(ApiError(err, status));
+        }
+    };
 
-    Ok(([(axum::http::header::CONTENT_TYPE, content_type)], data).into_response())
+    Ok((
+        StatusCode::OK,
+        [(axum::http::header::CONTENT_TYPE, "image/jpeg")],
+        bytes,
+    )
+        .into_response())
 }
 
 fn init_tracing() {
@@ -579,6 +1056,23 @@ struct Settings {
 struct StorageConfig {
     #[serde(default)]
     postgres_dsn: Option<String>,
+    /// 捕获帧存储后端: "local"（默认）或 "s3"
+    #[serde(default)]
+    backend: Option<String>,
+    #[serde(default)]
+    s3: Option<S3Config>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct S3Config {
+    #[serde(default)]
+    endpoint: Option<String>,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    #[serde(default)]
+    key_prefix: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -589,6 +1083,26 @@ struct ServerConfig {
     #[allow(dead_code)]
     // retained for config compatibility (used by deploy/nginx, not backend runtime)
     external_port: Option<u16>,
+    #[serde(default)]
+    compression: Option<CompressionConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompressionConfig {
+    /// 是否启用响应压缩，默认开启
+    #[serde(default = "default_compression_enabled")]
+    enabled: bool,
+    /// 低于该字节数的响应不压缩，默认 860
+    #[serde(default = "default_compression_min_size")]
+    min_size_bytes: u16,
+}
+
+fn default_compression_enabled() -> bool {
+    true
+}
+
+fn default_compression_min_size() -> u16 {
+    860
 }
 
 #[derive(Debug, Deserialize)]
@@ -607,6 +1121,8 @@ struct AuthConfig {
     secret: Option<String>,
     #[serde(default)]
     session_minutes: Option<i64>,
+    #[serde(default)]
+    refresh_days: Option<i64>,
 }
 
 fn load_config(path: impl AsRef<Path>) -> Result<Settings> {
@@ -648,22 +1164,53 @@ fn capture_root(settings: Option<&Settings>) -> Option<PathBuf> {
     })
 }
 
-fn sanitize_capture_path(root: &Path, candidate: &Path) -> Result<PathBuf> {
-    let root = root
-        .canonicalize()
-        .with_context(|| format!("无法解析捕获根目录 {}", root.display()))?;
-    let full = if candidate.is_absolute() {
-        candidate.to_path_buf()
-    } else {
-        root.join(candidate)
+/// 按配置选择捕获帧的存储后端；默认使用本地文件系统，`storage.backend = "s3"` 时
+/// 切换为 S3 兼容对象存储。
+async fn build_capture_storage(
+    settings: Option<&Settings>,
+) -> Result<Option<Arc<dyn CaptureStorage>>> {
+    let storage_config = settings.and_then(|s| s.storage.as_ref());
+    let backend = storage_config.and_then(|s| s.backend.as_deref()).unwrap_or("local");
+
+    match backend {
+        "s3" => {
+            let s3_config = storage_config
+                .and_then(|s| s.s3.as_ref())
+                .context("storage.backend 为 s3 时必须提供 storage.s3 配置")?;
+            let backend = storage::S3Storage::new(s3_config).await?;
+            Ok(Some(Arc::new(backend) as Arc<dyn CaptureStorage>))
+        }
+        _ => Ok(capture_root(settings)
+            .map(|root| Arc::new(storage::LocalStorage::new(root)) as Arc<dyn CaptureStorage>)),
     }
-    .canonicalize()
-    .with_context(|| format!("无法解析捕获文件路径 {}", candidate.display()))?;
+}
 
-    if !full.starts_with(&root) {
-        anyhow::bail!("捕获文件路径超出允许目录");
+type ListCompressionPredicate = And<SizeAbove, NotForContentType>;
+
+/// 为 JSON 列表/文档接口构建响应压缩层。抓拍图片/缩略图路由完全不挂载这个层
+/// （见 `main` 中的 `json_app`/`protected_image_routes` 拆分），因为图片的
+/// Content-Type 并不可靠：未知扩展名的帧会被 `CaptureStorage::content_type`
+/// 归为 `application/octet-stream`，仅靠 `NotForContentType::IMAGES` 前缀匹配
+/// 无法排除它，压缩后 206 响应的 `Content-Range` 也会和实际字节错位。这里保留
+/// `NotForContentType::IMAGES` 只是纵深防御；阈值以下的小响应同样不压缩。
+/// `server.compression.enabled = false` 时返回一个关闭所有编码算法的层，保持类型一致。
+fn build_compression_layer(settings: Option<&Settings>) -> CompressionLayer<ListCompressionPredicate> {
+    let config = settings
+        .and_then(|s| s.server.as_ref())
+        .and_then(|s| s.compression.as_ref());
+    let enabled = config.map(|c| c.enabled).unwrap_or_else(default_compression_enabled);
+    let min_size = config
+        .map(|c| c.min_size_bytes)
+        .unwrap_or_else(default_compression_min_size);
+
+    let predicate = SizeAbove::new(min_size).and(NotForContentType::IMAGES);
+    let layer = CompressionLayer::new().compress_when(predicate);
+
+    if enabled {
+        layer
+    } else {
+        layer.no_gzip().no_br().no_deflate().no_zstd()
     }
-    Ok(full)
 }
 
 fn repo_root() -> PathBuf {
@@ -698,41 +1245,6 @@ fn build_file_writer(path: &str) -> Option<tracing_appender::non_blocking::NonBl
     Some(writer)
 }
 
-fn build_auth_settings(settings: Option<&Settings>) -> AuthSettings {
-    let config_auth = settings.and_then(|s| s.auth.as_ref());
-
-    let username = config_auth
-        .and_then(|a| a.username.clone())
-        .unwrap_or_else(|| "admin".to_string());
-
-    let password = config_auth
-        .and_then(|a| a.password.clone())
-        .unwrap_or_else(|| "studyguardian".to_string());
-
-    let secret = config_auth
-        .and_then(|a| a.secret.clone())
-        .unwrap_or_else(|| "change-me-please".to_string());
-
-    let minutes = config_auth
-        .and_then(|a| a.session_minutes)
-        .unwrap_or(5)
-        .max(1);
-
-    AuthSettings {
-        username,
-        password,
-        session_minutes: minutes,
-        encoding: EncodingKey::from_secret(secret.as_bytes()),
-        decoding: DecodingKey::from_secret(secret.as_bytes()),
-    }
-}
-
-fn validate_token(token: &str, auth: &AuthSettings) -> Result<Claims> {
-    let validation = Validation::default();
-    let data = decode::<Claims>(token, &auth.decoding, &validation)?;
-    Ok(data.claims)
-}
-
 fn extract_token(req: &Request<Body>) -> Option<&str> {
     if let Some(header) = req
         .headers()
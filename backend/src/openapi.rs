@@ -0,0 +1,62 @@
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    ApiErrorBody, FaceCapture, ListParams, LoginRequest, LoginResponse, PostureEvent,
+    PostureListParams, RefreshRequest, RefreshResponse, ThumbnailQuery,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::login,
+        crate::refresh,
+        crate::logout,
+        crate::list_face_captures,
+        crate::get_face_capture_image,
+        crate::get_face_capture_thumb,
+        crate::list_posture_events,
+        crate::get_posture_event_image,
+        crate::get_posture_event_thumb,
+    ),
+    components(schemas(
+        LoginRequest,
+        LoginResponse,
+        RefreshRequest,
+        RefreshResponse,
+        FaceCapture,
+        PostureEvent,
+        ApiErrorBody,
+        ListParams,
+        PostureListParams,
+        ThumbnailQuery,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "auth", description = "登录与令牌签发"),
+        (name = "face-captures", description = "人脸抓拍记录"),
+        (name = "posture-events", description = "坐姿事件记录"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let Some(components) = openapi.components.as_mut() else {
+            return;
+        };
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
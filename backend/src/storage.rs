@@ -0,0 +1,185 @@
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::S3Config;
+
+/// 一个已解析的捕获帧文件的基本元信息，用于 ETag/Last-Modified 计算。
+pub struct FileStat {
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+/// 捕获帧的存储后端抽象：`frame_path` 是数据库中记录的逻辑路径，由具体实现解析为
+/// 本地文件路径或对象存储 key，调用方不需要关心两者的差异。
+#[async_trait]
+pub trait CaptureStorage: Send + Sync {
+    async fn stat(&self, frame_path: &str) -> Result<FileStat>;
+
+    /// 读取 `[start, end]`（闭区间，字节偏移）范围内的内容。
+    async fn open_range(
+        &self,
+        frame_path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>>;
+
+    fn content_type(&self, frame_path: &str) -> String {
+        mime_guess::from_path(frame_path)
+            .first_or_octet_stream()
+            .to_string()
+    }
+}
+
+/// 本地文件系统后端，沿用既有的路径穿越防护：所有帧文件必须位于 `root` 之下。
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn resolve(&self, frame_path: &str) -> Result<PathBuf> {
+        let root = self
+            .root
+            .canonicalize()
+            .with_context(|| format!("无法解析捕获根目录 {}", self.root.display()))?;
+
+        let candidate = Path::new(frame_path);
+        let full = if candidate.is_absolute() {
+            candidate.to_path_buf()
+        } else {
+            root.join(candidate)
+        }
+        .canonicalize()
+        .with_context(|| format!("无法解析捕获文件路径 {}", frame_path))?;
+
+        if !full.starts_with(&root) {
+            anyhow::bail!("捕获文件路径超出允许目录");
+        }
+        Ok(full)
+    }
+}
+
+#[async_trait]
+impl CaptureStorage for LocalStorage {
+    async fn stat(&self, frame_path: &str) -> Result<FileStat> {
+        let path = self.resolve(frame_path)?;
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .with_context(|| format!("无法读取文件元信息 {}", path.display()))?;
+        Ok(FileStat {
+            len: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        })
+    }
+
+    async fn open_range(
+        &self,
+        frame_path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+        let path = self.resolve(frame_path)?;
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .with_context(|| format!("无法打开文件 {}", path.display()))?;
+        file.seek(std::io::SeekFrom::Start(start))
+            .await
+            .context("定位文件读取位置失败")?;
+        Ok(Box::pin(file.take(end - start + 1)))
+    }
+}
+
+/// S3 兼容对象存储后端。`key_prefix` 限定了可访问的对象范围，拒绝任何试图跳出该
+/// 前缀的 `frame_path`（例如包含 `..` 片段）。
+pub struct S3Storage {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    key_prefix: String,
+}
+
+impl S3Storage {
+    pub async fn new(config: &S3Config) -> Result<Self> {
+        let credentials = aws_sdk_s3::config::Credentials::new(
+            config.access_key.clone(),
+            config.secret_key.clone(),
+            None,
+            None,
+            "studyguardian-capture-storage",
+        );
+
+        let mut builder = aws_sdk_s3::Config::builder()
+            .region(aws_sdk_s3::config::Region::new(config.region.clone()))
+            .credentials_provider(credentials)
+            .force_path_style(true);
+
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.endpoint_url(endpoint);
+        }
+
+        Ok(Self {
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            bucket: config.bucket.clone(),
+            key_prefix: config.key_prefix.clone().unwrap_or_default(),
+        })
+    }
+
+    fn scoped_key(&self, frame_path: &str) -> Result<String> {
+        if frame_path.contains("..") {
+            anyhow::bail!("捕获文件路径超出允许目录");
+        }
+        Ok(format!("{}{}", self.key_prefix, frame_path.trim_start_matches('/')))
+    }
+}
+
+#[async_trait]
+impl CaptureStorage for S3Storage {
+    async fn stat(&self, frame_path: &str) -> Result<FileStat> {
+        let key = self.scoped_key(frame_path)?;
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .context("S3 head_object 失败")?;
+
+        let len = head.content_length().unwrap_or(0).max(0) as u64;
+        let modified = head
+            .last_modified()
+            .and_then(|ts| SystemTime::try_from(*ts).ok())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        Ok(FileStat { len, modified })
+    }
+
+    async fn open_range(
+        &self,
+        frame_path: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+        let key = self.scoped_key(frame_path)?;
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={}-{}", start, end))
+            .send()
+            .await
+            .context("S3 get_object 失败")?;
+
+        Ok(Box::pin(output.body.into_async_read()))
+    }
+}
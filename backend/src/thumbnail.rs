@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::blurhash;
+use crate::storage::CaptureStorage;
+
+/// BlurHash 分量数：横向 4、纵向 3，兼顾细节与编码长度，与业界常见取值一致。
+const BLURHASH_X_COMPONENTS: u32 = 4;
+const BLURHASH_Y_COMPONENTS: u32 = 3;
+
+async fn read_all(storage: &dyn CaptureStorage, frame_path: &str) -> Result<Vec<u8>> {
+    let stat = storage.stat(frame_path).await?;
+    let mut reader = storage
+        .open_range(frame_path, 0, stat.len.saturating_sub(1))
+        .await?;
+    let mut buf = Vec::with_capacity(stat.len as usize);
+    reader
+        .read_to_end(&mut buf)
+        .await
+        .context("读取捕获帧失败")?;
+    Ok(buf)
+}
+
+/// 解码一帧捕获图片并计算其 BlurHash 占位字符串。
+pub async fn compute_blurhash(storage: &dyn CaptureStorage, frame_path: &str) -> Result<String> {
+    let bytes = read_all(storage, frame_path).await?;
+    tokio::task::spawn_blocking(move || {
+        let image = image::load_from_memory(&bytes)
+            .context("解码捕获帧失败")?
+            .to_rgb8();
+        let (width, height) = (image.width(), image.height());
+        blurhash::encode(
+            BLURHASH_X_COMPONENTS,
+            BLURHASH_Y_COMPONENTS,
+            width,
+            height,
+            image.as_raw(),
+        )
+    })
+    .await
+    .context("BlurHash 计算任务失败")?
+}
+
+/// 生成给定最大边长的缩略图 JPEG 字节，命中磁盘缓存时直接返回。`kind` 用于在缓存
+/// 目录中区分 `face-captures` 与 `posture-events`，避免两张表的 id 混淆。
+pub async fn thumbnail(
+    storage: &dyn CaptureStorage,
+    frame_path: &str,
+    cache_root: &Path,
+    kind: &str,
+    id: Uuid,
+    max_dim: u32,
+) -> Result<Vec<u8>> {
+    let cache_path: PathBuf = cache_root.join(kind).join(format!("{}_{}.jpg", id, max_dim));
+
+    if let Ok(cached) = tokio::fs::read(&cache_path).await {
+        return Ok(cached);
+    }
+
+    let bytes = read_all(storage, frame_path).await?;
+    let thumbnail_bytes = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let image = image::load_from_memory(&bytes).context("解码捕获帧失败")?;
+        // JPEG 编码器不支持带 alpha 通道的色彩类型(Rgba8/La8)，缩放后需先转换为
+        // RGB8，否则来源为带透明通道 PNG 的帧会在编码阶段失败。
+        let resized = image.thumbnail(max_dim, max_dim).to_rgb8();
+        let mut out = Vec::new();
+        resized
+            .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Jpeg)
+            .context("编码缩略图失败")?;
+        Ok(out)
+    })
+    .await
+    .context("缩略图生成任务失败")??;
+
+    if let Some(parent) = cache_path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            tracing::warn!("创建缩略图缓存目录失败 ({}): {}", parent.display(), err);
+        }
+    }
+    if let Err(err) = tokio::fs::write(&cache_path, &thumbnail_bytes).await {
+        tracing::warn!("写入缩略图缓存失败 ({}): {}", cache_path.display(), err);
+    }
+
+    Ok(thumbnail_bytes)
+}